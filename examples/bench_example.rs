@@ -0,0 +1,113 @@
+/**
+ * Example file demonstrating the built-in load/throughput benchmark harness.
+ *
+ * This shows how to replay a declarative JSON workload against a running
+ * node and collect latency/throughput numbers, the same way `cargo xtask
+ * bench` does for this crate's own regression tracking.
+ */
+
+use anyhow::Result;
+use kagi_macros::{action, service};
+use kagi_node::bench::{Benchmarker, Workload};
+use kagi_node::node::{Node, NodeConfig};
+use serde_json::json;
+
+/// Minimal stand-in for the "data" service exercised by `macros_example_2.rs`,
+/// just enough to give the `data/transform` and `data/increment` workload
+/// steps below a service to actually hit.
+#[service(name = "data", description = "Processes and transforms data")]
+struct DataProcessorService {
+    counter: u32,
+}
+
+impl DataProcessorService {
+    pub fn new() -> Self {
+        Self { counter: 0 }
+    }
+}
+
+impl DataProcessorService {
+    #[action(name = "transform")]
+    async fn transform(&self, data: &str) -> Result<String> {
+        Ok(data.to_uppercase())
+    }
+
+    #[action(name = "increment")]
+    async fn increment(&mut self, value: u32) -> Result<u32> {
+        self.counter += value;
+        Ok(self.counter)
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Create and configure the node
+    let config = NodeConfig::new(
+        "bench_node",
+        "./data",
+        "./data/db"
+    );
+
+    let mut node = Node::new(config).await?;
+    node.init().await?;
+    node.start().await?;
+
+    // Register the "data" service so the benchmark steps below have
+    // something to resolve "data/transform" and "data/increment" against.
+    let data_processor = DataProcessorService::new();
+    node.add_service(data_processor).await?;
+
+    // A workload is just a named list of steps - each a node.request or
+    // node.publish with a path, a params template, a concurrency level and
+    // an iteration count. Schema-validated and reusable across commits for
+    // regression tracking.
+    let workload: Workload = serde_json::from_value(json!({
+        "name": "data_service_smoke",
+        "steps": [
+            {
+                "name": "transform",
+                "kind": "request",
+                "path": "data/transform",
+                "params": { "data": "hello world" },
+                "concurrency": 8,
+                "iterations": 200
+            },
+            {
+                "name": "increment",
+                "kind": "request",
+                "path": "data/increment",
+                "params": { "value": 1 },
+                "concurrency": 4,
+                "iterations": 200
+            },
+            {
+                "name": "custom_event",
+                "kind": "publish",
+                "path": "events/custom",
+                "params": { "message": "bench", "timestamp": "", "data": "bench data" },
+                "concurrency": 16,
+                "iterations": 1000
+            }
+        ]
+    }))?;
+
+    // Replay the workload against the running node and collect per-step
+    // latency percentiles, error rate, and events/sec through the pub/sub bus
+    let benchmarker = Benchmarker::new(&node);
+    let report = benchmarker.run(&workload).await?;
+
+    for step in &report.steps {
+        println!(
+            "{}: p50={:?} p95={:?} p99={:?} error_rate={:.4} throughput={:.1}/s",
+            step.name, step.p50, step.p95, step.p99, step.error_rate, step.throughput_per_sec
+        );
+    }
+
+    // Emit machine-readable results so they can be uploaded to a collector
+    // endpoint and compared across commits
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    node.stop().await?;
+
+    Ok(())
+}
@@ -1,20 +1,27 @@
 /**
  * Example file demonstrating Runar macros for service definition
- * 
+ *
  * This example demonstrates:
  * 1. Using the service! macro to define services
  * 2. Proper field initialization in service structs
  * 3. Implementing action handlers with the action! macro
  * 4. Subscribing to events with the sub! macro
  * 5. Integration with the node architecture
+ * 6. Declaring parameter constraints on an action so the framework validates
+ *    request params before the handler body runs
+ * 7. Batching several requests into one atomic round trip with batch_request
+ * 8. Registering a recurring action with the #[schedule] macro
+ * 9. Dumping and restoring service state as a tracked task via snapshot/restore
+ * 10. Automatic per-action metrics and slow-handler warnings, exported via
+ *     internal/metrics/export
  */
 
 use anyhow::Result;
 use runar_common::types::ValueType;
-use runar_macros::{action, service, sub};
+use runar_macros::{action, schedule, service, sub};
 use runar_node::{
     node::{Node, NodeConfig},
-    services::{abstract_service::ServiceState, RequestContext, ServiceRequest, ServiceResponse},
+    services::{abstract_service::ServiceState, RequestContext, ResponseStatus, ServiceRequest, ServiceResponse},
 };
 use serde_json::json;
 use std::collections::HashMap;
@@ -30,6 +37,7 @@ pub struct Task {
     description: Option<String>,
     completed: bool,
     created_at: u64,
+    completed_at: Option<u64>,
 }
 
 /// ✅ CORRECT: Define a service using the service! macro
@@ -76,6 +84,7 @@ impl TaskManagerService {
             description,
             completed: false,
             created_at: Self::current_timestamp(),
+            completed_at: None,
         };
 
         // Store the task
@@ -104,8 +113,9 @@ impl TaskManagerService {
             
             if let Some(new_completed) = completed {
                 task.completed = new_completed;
+                task.completed_at = if new_completed { Some(Self::current_timestamp()) } else { None };
             }
-            
+
             return Some(task.clone());
         }
         
@@ -115,15 +125,87 @@ impl TaskManagerService {
     // Helper method to delete a task
     fn delete_task(&self, task_id: &str) -> bool {
         let mut tasks = self.tasks.lock().unwrap();
-        
+
         if tasks.remove(task_id).is_some() {
             let mut count = self.task_count.lock().unwrap();
             *count = count.saturating_sub(1);
             return true;
         }
-        
+
         false
     }
+
+    // Helper method to drop tasks that have been completed for longer than
+    // `max_age_secs`
+    fn purge_completed_older_than(&self, max_age_secs: u64) -> usize {
+        let now = Self::current_timestamp();
+        let mut tasks = self.tasks.lock().unwrap();
+        let before = tasks.len();
+
+        tasks.retain(|_, task| match task.completed_at {
+            Some(completed_at) => now.saturating_sub(completed_at) <= max_age_secs,
+            None => true,
+        });
+
+        let purged = before - tasks.len();
+        if purged > 0 {
+            let mut count = self.task_count.lock().unwrap();
+            *count = count.saturating_sub(purged as u32);
+        }
+
+        purged
+    }
+
+    // Serialize all in-memory state. Picked up by the `service!` macro to
+    // back `internal/state/dump`, which runs the dump as a tracked task
+    // rather than blocking the caller.
+    fn snapshot(&self) -> ValueType {
+        let tasks = self.tasks.lock().unwrap();
+        let task_count = *self.task_count.lock().unwrap();
+
+        ValueType::Json(json!({
+            "tasks": tasks.values().map(|task| json!({
+                "id": task.id,
+                "title": task.title,
+                "description": task.description,
+                "completed": task.completed,
+                "created_at": task.created_at,
+                "completed_at": task.completed_at
+            })).collect::<Vec<_>>(),
+            "task_count": task_count
+        }))
+    }
+
+    // Restore state previously produced by `snapshot`, used by
+    // `internal/state/load` when migrating or restoring a node.
+    fn restore(&self, state: ValueType) {
+        let ValueType::Json(json) = state else { return };
+        let Some(entries) = json.get("tasks").and_then(|v| v.as_array()) else { return };
+
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.clear();
+
+        for entry in entries {
+            let (Some(id), Some(title)) = (
+                entry.get("id").and_then(|v| v.as_str()),
+                entry.get("title").and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+
+            tasks.insert(id.to_string(), Task {
+                id: id.to_string(),
+                title: title.to_string(),
+                description: entry.get("description").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                completed: entry.get("completed").and_then(|v| v.as_bool()).unwrap_or(false),
+                created_at: entry.get("created_at").and_then(|v| v.as_u64()).unwrap_or_else(Self::current_timestamp),
+                completed_at: entry.get("completed_at").and_then(|v| v.as_u64()),
+            });
+        }
+
+        let mut count = self.task_count.lock().unwrap();
+        *count = tasks.len() as u32;
+    }
 }
 
 /// ✅ CORRECT: Define service actions using the action! macro
@@ -159,184 +241,190 @@ impl TaskManagerService {
     }
     
     // Action to create a new task
-    #[action]
+    //
+    // `require`/`max_len` run as request-level argument validation before the
+    // handler body is invoked, the same way request-level limits like
+    // max_width/max_height/max_area/max_file_size/allow_* gate other request
+    // types in this crate. A failed constraint returns a structured
+    // ServiceResponse::error without ever reaching this body, so `json` below
+    // is guaranteed to carry a `title` field (its type is checked below).
+    // `warn_after` logs a warning if the handler is still pending past that
+    // threshold, to catch a future handler body that blocks the executor;
+    // invocation counts, error counts, and latency are recorded for every
+    // `#[action]` automatically and surface through
+    // "internal/metrics/export" without any of this being written by hand.
+    #[action(require(title), max_len(title = 256), warn_after = "200ms")]
     async fn create_task(&self, request: ServiceRequest) -> Result<ServiceResponse, anyhow::Error> {
-        // Extract parameters from request
-        if let Some(params) = request.params {
-            match params {
-                ValueType::Json(json) => {
-                    // Extract task properties
-                    let title = json.get("title")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Title is required"))?
-                        .to_string();
-                    
-                    let description = json.get("description")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string());
-                    
-                    // Create the task
-                    let new_task = self.add_task(title, description);
-                    
-                    // Publish task created event
-                    request.context.publish(
-                        "tasks/events/created",
-                        ValueType::Json(json!({
-                            "task_id": new_task.id,
-                            "timestamp": Self::current_timestamp()
-                        }))
-                    ).await?;
-                    
-                    // Return the created task
-                    let response_data = json!({
-                        "task": {
-                            "id": new_task.id,
-                            "title": new_task.title,
-                            "description": new_task.description,
-                            "completed": new_task.completed,
-                            "created_at": new_task.created_at
-                        }
-                    });
-                    
-                    ServiceResponse::success("Task created successfully", Some(ValueType::Json(response_data)))
-                },
-                _ => ServiceResponse::error("Invalid request format")
+        let params = request.params.ok_or_else(|| anyhow::anyhow!("No parameters provided"))?;
+        let ValueType::Json(json) = params else {
+            return ServiceResponse::error("Invalid request format");
+        };
+
+        // `#[action(require(title))]` only validates that `title` is
+        // present, not that it's a string, so a malformed request (e.g.
+        // `title: 123`) still needs to be rejected gracefully here.
+        let Some(title) = json.get("title").and_then(|v| v.as_str()) else {
+            return ServiceResponse::error("title must be a string");
+        };
+        let title = title.to_string();
+        let description = json.get("description")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
+        // Create the task
+        let new_task = self.add_task(title, description);
+
+        // Publish task created event
+        request.context.publish(
+            "tasks/events/created",
+            ValueType::Json(json!({
+                "task_id": new_task.id,
+                "timestamp": Self::current_timestamp()
+            }))
+        ).await?;
+
+        // Return the created task
+        let response_data = json!({
+            "task": {
+                "id": new_task.id,
+                "title": new_task.title,
+                "description": new_task.description,
+                "completed": new_task.completed,
+                "created_at": new_task.created_at
             }
-        } else {
-            ServiceResponse::error("No parameters provided")
-        }
+        });
+
+        ServiceResponse::success("Task created successfully", Some(ValueType::Json(response_data)))
     }
-    
+
     // Action to get a specific task by ID
-    #[action]
+    #[action(require(id))]
     async fn get_task(&self, request: ServiceRequest) -> Result<ServiceResponse, anyhow::Error> {
-        // Extract task ID from request
-        if let Some(params) = request.params {
-            match params {
-                ValueType::Json(json) => {
-                    // Get the task ID
-                    let task_id = json.get("id")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Task ID is required"))?;
-                    
-                    // Find the task
-                    if let Some(task) = self.find_task(task_id) {
-                        // Return the task
-                        let response_data = json!({
-                            "task": {
-                                "id": task.id,
-                                "title": task.title,
-                                "description": task.description,
-                                "completed": task.completed,
-                                "created_at": task.created_at
-                            }
-                        });
-                        
-                        ServiceResponse::success("Task retrieved successfully", Some(ValueType::Json(response_data)))
-                    } else {
-                        ServiceResponse::error("Task not found")
-                    }
-                },
-                _ => ServiceResponse::error("Invalid request format")
-            }
+        let params = request.params.ok_or_else(|| anyhow::anyhow!("No parameters provided"))?;
+        let ValueType::Json(json) = params else {
+            return ServiceResponse::error("Invalid request format");
+        };
+
+        // `#[action(require(id))]` only validates that `id` is present, not
+        // that it's a string, so a malformed request (e.g. `id: 123`) still
+        // needs to be rejected gracefully here instead of panicking.
+        let Some(task_id) = json.get("id").and_then(|v| v.as_str()) else {
+            return ServiceResponse::error("id must be a string");
+        };
+
+        // Find the task
+        if let Some(task) = self.find_task(task_id) {
+            // Return the task
+            let response_data = json!({
+                "task": {
+                    "id": task.id,
+                    "title": task.title,
+                    "description": task.description,
+                    "completed": task.completed,
+                    "created_at": task.created_at
+                }
+            });
+
+            ServiceResponse::success("Task retrieved successfully", Some(ValueType::Json(response_data)))
         } else {
-            ServiceResponse::error("No parameters provided")
+            ServiceResponse::error("Task not found")
         }
     }
-    
+
     // Action to update a task
-    #[action]
+    #[action(require(id), max_len(title = 256))]
     async fn update_task(&self, request: ServiceRequest) -> Result<ServiceResponse, anyhow::Error> {
-        // Extract parameters from request
-        if let Some(params) = request.params {
-            match params {
-                ValueType::Json(json) => {
-                    // Get the task ID
-                    let task_id = json.get("id")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Task ID is required"))?;
-                    
-                    // Get update fields
-                    let title = json.get("title").and_then(|v| v.as_str()).map(|s| s.to_string());
-                    let description = json.get("description").and_then(|v| v.as_str()).map(|s| s.to_string());
-                    let completed = json.get("completed").and_then(|v| v.as_bool());
-                    
-                    // Update the task
-                    if let Some(updated_task) = self.update_task(task_id, title, description, completed) {
-                        // Publish task updated event if completed status changed
-                        if let Some(true) = completed {
-                            request.context.publish(
-                                "tasks/events/completed",
-                                ValueType::Json(json!({
-                                    "task_id": updated_task.id,
-                                    "timestamp": Self::current_timestamp()
-                                }))
-                            ).await?;
-                        }
-                        
-                        // Return the updated task
-                        let response_data = json!({
-                            "task": {
-                                "id": updated_task.id,
-                                "title": updated_task.title,
-                                "description": updated_task.description,
-                                "completed": updated_task.completed,
-                                "created_at": updated_task.created_at
-                            }
-                        });
-                        
-                        ServiceResponse::success("Task updated successfully", Some(ValueType::Json(response_data)))
-                    } else {
-                        ServiceResponse::error("Task not found")
-                    }
-                },
-                _ => ServiceResponse::error("Invalid request format")
+        let params = request.params.ok_or_else(|| anyhow::anyhow!("No parameters provided"))?;
+        let ValueType::Json(json) = params else {
+            return ServiceResponse::error("Invalid request format");
+        };
+
+        // `#[action(require(id))]` only validates that `id` is present, not
+        // that it's a string, so a malformed request (e.g. `id: 123`) still
+        // needs to be rejected gracefully here instead of panicking.
+        let Some(task_id) = json.get("id").and_then(|v| v.as_str()) else {
+            return ServiceResponse::error("id must be a string");
+        };
+
+        // Get update fields
+        let title = json.get("title").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let description = json.get("description").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let completed = json.get("completed").and_then(|v| v.as_bool());
+
+        // Update the task
+        if let Some(updated_task) = self.update_task(task_id, title, description, completed) {
+            // Publish task updated event if completed status changed
+            if let Some(true) = completed {
+                request.context.publish(
+                    "tasks/events/completed",
+                    ValueType::Json(json!({
+                        "task_id": updated_task.id,
+                        "timestamp": Self::current_timestamp()
+                    }))
+                ).await?;
             }
+
+            // Return the updated task
+            let response_data = json!({
+                "task": {
+                    "id": updated_task.id,
+                    "title": updated_task.title,
+                    "description": updated_task.description,
+                    "completed": updated_task.completed,
+                    "created_at": updated_task.created_at
+                }
+            });
+
+            ServiceResponse::success("Task updated successfully", Some(ValueType::Json(response_data)))
         } else {
-            ServiceResponse::error("No parameters provided")
+            ServiceResponse::error("Task not found")
         }
     }
-    
+
     // Action to delete a task
-    #[action]
+    #[action(require(id))]
     async fn delete_task(&self, request: ServiceRequest) -> Result<ServiceResponse, anyhow::Error> {
-        // Extract task ID from request
-        if let Some(params) = request.params {
-            match params {
-                ValueType::Json(json) => {
-                    // Get the task ID
-                    let task_id = json.get("id")
-                        .and_then(|v| v.as_str())
-                        .ok_or_else(|| anyhow::anyhow!("Task ID is required"))?;
-                    
-                    // Delete the task
-                    if self.delete_task(task_id) {
-                        // Publish task deleted event
-                        request.context.publish(
-                            "tasks/events/deleted",
-                            ValueType::Json(json!({
-                                "task_id": task_id,
-                                "timestamp": Self::current_timestamp()
-                            }))
-                        ).await?;
-                        
-                        ServiceResponse::success("Task deleted successfully", None)
-                    } else {
-                        ServiceResponse::error("Task not found")
-                    }
-                },
-                _ => ServiceResponse::error("Invalid request format")
-            }
+        let params = request.params.ok_or_else(|| anyhow::anyhow!("No parameters provided"))?;
+        let ValueType::Json(json) = params else {
+            return ServiceResponse::error("Invalid request format");
+        };
+
+        // `#[action(require(id))]` only validates that `id` is present, not
+        // that it's a string, so a malformed request (e.g. `id: 123`) still
+        // needs to be rejected gracefully here instead of panicking.
+        let Some(task_id) = json.get("id").and_then(|v| v.as_str()) else {
+            return ServiceResponse::error("id must be a string");
+        };
+
+        // Delete the task
+        if self.delete_task(task_id) {
+            // Publish task deleted event
+            request.context.publish(
+                "tasks/events/deleted",
+                ValueType::Json(json!({
+                    "task_id": task_id,
+                    "timestamp": Self::current_timestamp()
+                }))
+            ).await?;
+
+            ServiceResponse::success("Task deleted successfully", None)
         } else {
-            ServiceResponse::error("No parameters provided")
+            ServiceResponse::error("Task not found")
         }
     }
     
     // Subscribe to events during service initialization
-    #[sub(topic = "tasks/commands/purge")]
+    //
+    // `retries`/`backoff` make failed deliveries retry with exponential
+    // backoff instead of being dropped; once the attempt count is exhausted
+    // the payload and error are republished to
+    // "tasks/commands/purge.deadletter" instead of vanishing. Clearing the
+    // whole task map is naturally idempotent, so re-running this handler on
+    // retry is safe without checking `context.attempt()`.
+    #[sub(topic = "tasks/commands/purge", retries = 3, backoff = "exp")]
     async fn handle_purge_command(&self, _payload: ValueType, context: &RequestContext) -> Result<(), anyhow::Error> {
-        println!("Received purge command, clearing all tasks");
-        
+        println!("Received purge command (attempt {}), clearing all tasks", context.attempt());
+
         // Clear all tasks
         let mut tasks = self.tasks.lock().unwrap();
         let task_count = tasks.len();
@@ -357,6 +445,20 @@ impl TaskManagerService {
         
         Ok(())
     }
+
+    // Periodically drop completed tasks older than a day. `every` registers
+    // a persistent schedule entry keyed by this service's path and action
+    // name, so the schedule survives a node restart instead of needing to be
+    // re-armed from an init hook; the scheduler loop tracks next-run time,
+    // interval, and last result per entry and reschedules itself after each
+    // run.
+    #[schedule(every = "24h")]
+    #[action]
+    async fn purge_stale(&self, _request: ServiceRequest) -> Result<ServiceResponse, anyhow::Error> {
+        let purged = self.purge_completed_older_than(24 * 60 * 60);
+        println!("Scheduled purge: removed {} stale completed task(s)", purged);
+        ServiceResponse::success("Stale tasks purged", Some(ValueType::Json(json!({ "purged_count": purged }))))
+    }
 }
 
 // Define an analytics service to demonstrate event handling
@@ -392,8 +494,17 @@ impl TaskAnalyticsService {
     }
     
     // Subscribe to task created events
-    #[sub(topic = "tasks/events/created")]
-    async fn handle_task_created(&self, _payload: ValueType, _context: &RequestContext) -> Result<(), anyhow::Error> {
+    //
+    // Counting isn't naturally idempotent, so on retry (`context.attempt() >
+    // 0`) we log it but still increment: a retry only happens after the
+    // previous attempt returned `Err`, meaning the increment didn't
+    // land. After `retries` failed attempts the event is republished to
+    // "tasks/events/created.deadletter" instead of being silently lost.
+    #[sub(topic = "tasks/events/created", retries = 5, backoff = "exp")]
+    async fn handle_task_created(&self, _payload: ValueType, context: &RequestContext) -> Result<(), anyhow::Error> {
+        if context.attempt() > 0 {
+            println!("Analytics: retrying task created event (attempt {})", context.attempt());
+        }
         let mut count = self.total_created.lock().unwrap();
         *count += 1;
         println!("Analytics: Task created event received. Total created: {}", *count);
@@ -474,20 +585,39 @@ async fn main() -> Result<()> {
         }
     }
     
-    // Create some tasks
+    // Create some tasks in a single atomic batch instead of five separate
+    // round trips: if any create fails, none of them land and no
+    // "tasks/events/created" events are published.
     println!("\nCreating tasks...");
-    for i in 1..=5 {
-        let task_data = json!({
-            "title": format!("Example Task {}", i),
-            "description": format!("This is description for task {}", i)
-        });
-        
-        node.request(
-            "task_manager/create_task",
-            ValueType::Json(task_data),
-        ).await?;
+    let create_ops = (1..=5)
+        .map(|i| {
+            (
+                "task_manager/create_task".to_string(),
+                ValueType::Json(json!({
+                    "title": format!("Example Task {}", i),
+                    "description": format!("This is description for task {}", i)
+                })),
+            )
+        })
+        .collect();
+
+    let create_results = node.batch_request(create_ops, true).await?;
+    for result in &create_results {
+        if result.status != ResponseStatus::Success {
+            println!("Batch create failed: {:?}", result);
+        }
     }
-    
+
+    // Inspect the schedule the #[schedule] macro registered for
+    // task_manager/purge_stale
+    println!("\nListing scheduled entries...");
+    let schedule_response = node.request(
+        "internal/scheduler/list",
+        ValueType::Null,
+    ).await?;
+
+    println!("Scheduled entries: {:?}", schedule_response);
+
     // Complete a couple of tasks
     println!("\nCompleting tasks...");
     for i in 1..=2 {
@@ -521,7 +651,40 @@ async fn main() -> Result<()> {
     ).await?;
     
     println!("Task list response: {:?}", list_response);
-    
+
+    // Dump task_manager's state as a tracked task instead of a blocking call
+    println!("\nDumping task_manager state...");
+    let dump_enqueued = node.request(
+        "internal/state/dump",
+        ValueType::Json(json!({ "service": "task_manager" })),
+    ).await?;
+
+    let dump_task_id = match &dump_enqueued.data {
+        Some(ValueType::Json(json)) => json.get("task_id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+        _ => String::new(),
+    };
+    println!("Dump task enqueued: {}", dump_task_id);
+
+    // Poll until "state/events/dump_completed" fires; a real client could
+    // subscribe to that topic instead of polling
+    loop {
+        let status_response = node.request(
+            "internal/tasks/status",
+            ValueType::Json(json!({ "task_id": dump_task_id })),
+        ).await?;
+
+        if let Some(ValueType::Json(json)) = &status_response.data {
+            if let Some(status) = json.get("status").and_then(|v| v.as_str()) {
+                println!("Dump task status: {}", status);
+                if status == "done" || status == "failed" {
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    }
+
     // Get analytics
     println!("\nRetrieving analytics...");
     let analytics_response = node.request(
@@ -550,7 +713,19 @@ async fn main() -> Result<()> {
     ).await?;
     
     println!("Final analytics: {:?}", final_analytics);
-    
+
+    // Export the metrics the #[action]/#[sub] macros collected automatically
+    // for every handler above, in Prometheus text format
+    println!("\nExporting metrics...");
+    let metrics_response = node.request(
+        "internal/metrics/export",
+        ValueType::Null,
+    ).await?;
+
+    if let Some(ValueType::String(metrics_text)) = &metrics_response.data {
+        println!("{}", metrics_text);
+    }
+
     println!("\nExample completed successfully!");
     Ok(())
 } 
\ No newline at end of file
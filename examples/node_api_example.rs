@@ -1,6 +1,6 @@
 /**
  * Example file demonstrating the Runar Node API
- * 
+ *
  * This example demonstrates:
  * 1. Creating and configuring a node
  * 2. Creating a custom service that handles events
@@ -9,6 +9,12 @@
  * 5. Using vmap! for clean parameter extraction
  * 6. Proper service implementation following the AbstractService trait
  * 7. Event handling and storage
+ * 8. Streaming a large result set with request_stream instead of buffering it
+ * 9. Filtered, scoped event subscriptions with sync/async delivery modes
+ * 10. Event synthesis so late subscribers catch up on current state
+ * 11. Selecting a compact binary wire format for node-to-node transport
+ * 12. Declaring per-operation guards instead of hand-rolled validation
+ * 13. Relay configuration for NAT-bound nodes, gated by time-bounded capability keys
  */
 
 use anyhow::Result;
@@ -17,18 +23,26 @@ use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use futures::StreamExt;
 use runar_common::types::ValueType;
 use runar_node::{
     services::{
         abstract_service::{AbstractService, ServiceMetadata, ServiceState},
-        ResponseStatus, ServiceRequest, ServiceResponse,
-        RequestContext,
+        guards::{Guard, OperationPattern, RequiresParams},
+        EventFilter, EventMode, EventSynthesisProvider, ResponseStatus, ServiceRequest,
+        ServiceResponse, RequestContext, SubscriptionOptions,
     },
     node::Node,
     node::NodeConfig,
+    transport::WireFormat,
 };
 use serde_json::json;
 use tempfile::tempdir;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+// Bounded channel capacity for a single response stream
+const STREAM_CHANNEL_CAPACITY: usize = 16;
 
 // Event struct to represent events handled by our service
 pub struct Event {
@@ -61,6 +75,21 @@ async fn main() -> Result<()> {
         listen_addr: None,
         p2p_config: None,
         test_network_ids: None,
+        // Postcard gives compact, fast framing for the event-heavy workloads this
+        // example favors; peers that don't support it fall back to Json during
+        // the handshake, so the in-memory ValueType API is unaffected either way.
+        wire_format: WireFormat::Postcard,
+        // This example runs a single local node, so it doesn't need to register
+        // with a relay to be reachable from behind a NAT/firewall. A node that
+        // does would set relay_addr and issue capability_keys scoped to the
+        // service paths peers are allowed to reach, e.g.:
+        //   capability_keys: vec![CapabilityKey {
+        //       not_before: SystemTime::now(),
+        //       not_after: SystemTime::now() + Duration::from_secs(3600),
+        //       allowed_prefixes: vec!["event_handler".to_string()],
+        //   }],
+        relay_addr: None,
+        capability_keys: Vec::new(),
     };
     
     //--------------------------
@@ -142,7 +171,27 @@ async fn main() -> Result<()> {
     
     println!("Current events: {:?}", events_data);
     println!("Total events: {}", count);
-    
+
+    //--------------------------
+    // 7. Streaming a Large Result Set
+    //--------------------------
+
+    // ✅ CORRECT: Using request_stream to receive chunks incrementally
+    // instead of buffering the whole result set in one response
+    println!("Streaming stored events...");
+    let mut event_stream = node.request_stream(
+        "event_handler/get_events_stream",
+        ValueType::Null,
+    ).await?;
+
+    let mut streamed_count = 0;
+    while let Some(chunk) = event_stream.next().await {
+        let chunk = chunk?;
+        println!("Received streamed event chunk: {:?}", chunk);
+        streamed_count += 1;
+    }
+    println!("Streamed {} event chunks", streamed_count);
+
     println!("Example completed successfully!");
     
     Ok(())
@@ -269,7 +318,17 @@ impl AbstractService for EventHandlerService {
     fn description(&self) -> &str {
         "A service for handling and storing events"
     }
-    
+
+    /// Declares per-operation guards so the dispatch path rejects malformed
+    /// requests before they reach `handle_request`, instead of each operation
+    /// hand-rolling its own validation.
+    fn guards(&self) -> Vec<(OperationPattern, Box<dyn Guard>)> {
+        vec![(
+            OperationPattern::exact("store_event"),
+            Box::new(RequiresParams),
+        )]
+    }
+
     /// Initializes the service when the node starts
     /// This is where subscriptions should be set up
     async fn init(&mut self, context: &RequestContext) -> Result<(), anyhow::Error> {
@@ -277,15 +336,34 @@ impl AbstractService for EventHandlerService {
         
         // ✅ CORRECT: Subscribe to events during initialization
         let self_clone = self.clone();
-        
-        // Subscribe to a specific topic - we'll use "app/events" for this example
-        context.subscribe("app/events", move |payload| {
-            let service = self_clone.clone();
-            Box::pin(async move {
-                service.process_event(payload).await
-            })
-        }).await?;
-        
+
+        // Register this service as the synthesis provider for "app/events" so
+        // subscribers that join after events have already been published can
+        // still request a catch-up burst reflecting current state.
+        context.register_synthesis_provider("app/events", Arc::new(self_clone.clone())).await?;
+
+        // Subscribe to a specific topic - we'll use "app/events" for this example.
+        // Only events whose "type" field is "test_event" wake this handler, the
+        // publisher awaits handler completion before context.publish returns
+        // (ordering/back-pressure), delivery is scoped to events originating
+        // from the "event_handler" service path itself, and synthesis delivers
+        // a catch-up burst of existing events before the live stream begins.
+        context.subscribe_with_options(
+            "app/events",
+            SubscriptionOptions {
+                filter: Some(EventFilter::field_eq("type", "test_event")),
+                mode: EventMode::Sync,
+                scope: Some("event_handler".to_string()),
+                synthesize: true,
+            },
+            move |payload| {
+                let service = self_clone.clone();
+                Box::pin(async move {
+                    service.process_event(payload).await
+                })
+            },
+        ).await?;
+
         println!("Event subscription registered");
         
         // Update state
@@ -322,25 +400,17 @@ impl AbstractService for EventHandlerService {
         // ✅ CORRECT: Match on the operation and delegate to specific handlers
         match request.operation.as_str() {
             "store_event" => {
-                // Extract the event data from the request
-                if let Some(data) = request.params {
-                    // Process the event
-                    self.process_event(data).await?;
-                    
-                    // Return success response
-                    Ok(ServiceResponse {
-                        status: ResponseStatus::Success,
-                        message: "Event stored successfully".to_string(),
-                        data: Some(ValueType::String("OK".to_string())),
-                    })
-                } else {
-                    // Return error if no data provided
-                    Ok(ServiceResponse {
-                        status: ResponseStatus::Error,
-                        message: "No event data provided".to_string(),
-                        data: None,
-                    })
-                }
+                // ✅ CORRECT: The RequiresParams guard already rejected this
+                // request before it reached us if params were missing, so
+                // handle_request no longer needs its own presence check.
+                let data = request.params.expect("RequiresParams guard guarantees params");
+                self.process_event(data).await?;
+
+                Ok(ServiceResponse {
+                    status: ResponseStatus::Success,
+                    message: "Event stored successfully".to_string(),
+                    data: Some(ValueType::String("OK".to_string())),
+                })
             },
             "get_events" => {
                 // Get a reference to stored events
@@ -391,4 +461,104 @@ impl AbstractService for EventHandlerService {
             }
         }
     }
-} 
\ No newline at end of file
+
+    /// Handles requests that should be streamed back chunk by chunk instead of
+    /// being buffered into a single response.
+    ///
+    /// Only `get_events_stream` is streamed chunk by chunk; every other
+    /// operation is answered by `handle_request` and its response is returned
+    /// as a single-chunk stream.
+    async fn handle_request_stream(
+        &self,
+        request: ServiceRequest,
+    ) -> Result<ReceiverStream<Result<ValueType, anyhow::Error>>, anyhow::Error> {
+        match request.operation.as_str() {
+            "get_events_stream" => {
+                let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+                let events = self.events.lock().unwrap();
+
+                // Snapshot the events so the streaming task doesn't need to hold
+                // the lock while sending chunks.
+                let mut chunks = Vec::with_capacity(events.len());
+                for event in events.iter() {
+                    let mut event_map = std::collections::HashMap::new();
+                    event_map.insert("id".to_string(), ValueType::String(event.id.clone()));
+                    event_map.insert("type".to_string(), ValueType::String(event.event_type.clone()));
+
+                    let timestamp = event.timestamp
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    event_map.insert("timestamp".to_string(), ValueType::Number(timestamp as f64));
+
+                    if let Some(ref data) = event.data {
+                        event_map.insert("data".to_string(), data.clone());
+                    }
+
+                    chunks.push(ValueType::Map(event_map));
+                }
+                drop(events);
+
+                tokio::spawn(async move {
+                    for (seq, chunk) in chunks.into_iter().enumerate() {
+                        println!("Streaming event chunk #{}", seq);
+                        if tx.send(Ok(chunk)).await.is_err() {
+                            // Receiver dropped; stop streaming early
+                            break;
+                        }
+                    }
+                    // Dropping `tx` here closes the channel, which the node
+                    // reports to the caller as the stream's end-of-stream frame.
+                });
+
+                Ok(ReceiverStream::new(rx))
+            },
+            _ => {
+                // Wrap the unary handle_request response in a single-chunk stream
+                let response = self.handle_request(request).await?;
+                let chunk = match response.status {
+                    ResponseStatus::Success => Ok(response.data.unwrap_or(ValueType::Null)),
+                    _ => Err(anyhow::anyhow!(response.message)),
+                };
+                let (tx, rx) = mpsc::channel(1);
+                let _ = tx.send(chunk).await;
+                Ok(ReceiverStream::new(rx))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EventSynthesisProvider for EventHandlerService {
+    /// Synthesizes a catch-up burst of "current state" events for a subscriber
+    /// that joins after some events have already been published. Each stored
+    /// event is replayed as if it had just occurred, filtered the same way a
+    /// live event would be.
+    async fn synthesize(&self, filter: &EventFilter) -> Vec<ValueType> {
+        let events = self.events.lock().unwrap();
+        let mut synthesized = Vec::new();
+
+        for event in events.iter() {
+            let mut event_map = std::collections::HashMap::new();
+            event_map.insert("id".to_string(), ValueType::String(event.id.clone()));
+            event_map.insert("type".to_string(), ValueType::String(event.event_type.clone()));
+
+            let timestamp = event.timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            event_map.insert("timestamp".to_string(), ValueType::Number(timestamp as f64));
+
+            if let Some(ref data) = event.data {
+                event_map.insert("data".to_string(), data.clone());
+            }
+
+            let candidate = ValueType::Map(event_map);
+            if filter.matches(&candidate) {
+                synthesized.push(candidate);
+            }
+        }
+
+        synthesized
+    }
+}
\ No newline at end of file
@@ -6,7 +6,9 @@ use runar_node::{
     Node,
 };
 use runar_gateway::{
-    Gateway, GatewayConfig, Next, hyper::{Request, Response, Body}
+    Gateway, GatewayConfig, Next, hyper::{Request, Response, Body},
+    auth::{JwtAlgorithm, JwtConfig, JwtMiddleware},
+    policy::{PolicyConfig, PolicyMiddleware},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -74,32 +76,33 @@ impl UserService {
     }
 }
 
-// Define an auth middleware
-#[middleware]
-pub struct AuthMiddleware;
+// Configure the built-in JWT middleware: it verifies the bearer token's
+// signature and exp/nbf claims, enforces any per-route required scopes
+// against the `scope` claim, and on success stores the decoded claims on
+// the request context so `#[from_context("claim_name")]` route parameters
+// can be populated from them. A missing/invalid/expired token or an
+// insufficient scope short-circuits with 401 before `next.run` is called.
+fn auth_middleware() -> JwtMiddleware {
+    JwtMiddleware::new(JwtConfig {
+        algorithm: JwtAlgorithm::HS256,
+        // In a real deployment this comes from the environment, not a
+        // literal in source.
+        verification_key: std::env::var("JWT_SIGNING_KEY")
+            .unwrap_or_else(|_| "dev-only-secret".to_string()),
+        required_claims: vec!["sub".to_string()],
+    })
+}
 
-impl AuthMiddleware {
-    pub fn new() -> Self {
-        Self {}
-    }
-    
-    #[action]
-    async fn handle_request(&self, req: &Request<hyper::Body>, next: Next<'_>) -> Result<Response<hyper::Body>> {
-        // In a real app, we would validate a token here
-        let token = req.headers()
-            .get("Authorization")
-            .and_then(|h| h.to_str().ok())
-            .and_then(|s| s.strip_prefix("Bearer "));
-            
-        if token.is_none() {
-            // For this example, we'll only check if the header exists
-            // In a real app, we would validate the token
-            return Err(anyhow::anyhow!("Unauthorized"));
-        }
-        
-        // Continue processing
-        next.run(req).await
-    }
+// Casbin-style RBAC: the enforcer loads an access-control model plus policy
+// rules and, for each request, derives subject/object/action from the JWT
+// claims, matched route, and HTTP method respectively. It lives behind an
+// Arc<RwLock<..>> so `reload_policies` below can swap in a new policy file
+// at runtime without restarting the gateway.
+fn policy_middleware() -> PolicyMiddleware {
+    PolicyMiddleware::new(PolicyConfig {
+        model_path: "./config/rbac_model.conf".to_string(),
+        policy_path: "./config/rbac_policy.csv".to_string(),
+    })
 }
 
 // Define the API gateway
@@ -108,7 +111,7 @@ impl AuthMiddleware {
     host = "0.0.0.0",
     port = 8080,
     services = [UserService],
-    middleware = [AuthMiddleware::new()]
+    middleware = [auth_middleware(), policy_middleware()]
 )]
 pub struct ApiGateway;
 
@@ -136,11 +139,24 @@ impl ApiGateway {
         self.context.request("user_service", "get_user", { id }).await
     }
     
-    // Protected endpoints
-    #[route(GET, "/api/profile", middleware = [AuthMiddleware])]
-    async fn get_profile(&self, #[from_context] user_id: Uuid) -> Result<User> {
+    // Protected endpoints. `scopes` is enforced by JwtMiddleware against the
+    // `scope` claim before the handler runs, and `#[from_context("sub")]`
+    // pulls the `sub` claim the middleware decoded straight into `user_id`.
+    // PolicyMiddleware then enforces (subject, "user_service", "read") by
+    // default, derived from the matched service and GET; `get_profile`
+    // doesn't map onto another user's object cleanly, so it overrides
+    // `object` explicitly to "self_profile" instead.
+    #[route(GET, "/api/profile", middleware = [JwtMiddleware], scopes = ["profile:read"], object = "self_profile", action = "read")]
+    async fn get_profile(&self, #[from_context("sub")] user_id: Uuid) -> Result<User> {
         self.context.request("user_service", "get_user", { id: user_id }).await
     }
+
+    // Admin-only: reload the RBAC model/policy from disk without
+    // restarting the gateway.
+    #[route(POST, "/api/admin/policies/reload", middleware = [JwtMiddleware], scopes = ["admin"])]
+    async fn reload_policies(&self) -> Result<()> {
+        self.gateway.middleware::<PolicyMiddleware>().reload().await
+    }
 }
 
 // Main application entry point
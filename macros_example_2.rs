@@ -6,9 +6,10 @@
  */
 
 use anyhow::Result;
+use futures::StreamExt;
 use kagi_macros::{action, service, subscribe};
 use kagi_node::services::{
-    AbstractService, RequestContext, ServiceResponse, ValueType, ResponseStatus
+    AbstractService, RequestContext, ResponseSink, ServiceResponse, ValueType, ResponseStatus
 };
 use kagi_node::vmap;
 
@@ -20,14 +21,19 @@ use kagi_node::vmap;
 /// - path: The routing path for this service (optional, defaults to name)
 /// - description: Human-readable description (optional)
 /// - version: Version string (optional, defaults to "0.1.0")
+/// - persistent_state: when true, annotated struct fields are auto-snapshotted
+///   through the node's KV-backed state subsystem (persisted under the node's
+///   ./data/db path) instead of living purely in memory
 #[service(
     name = "data",
-    // path = "data", ommited on purpose to desmostrate that in this csae will uyse name as path  
+    // path = "data", ommited on purpose to desmostrate that in this csae will uyse name as path
     description = "Processes and transforms data",
-    version = "1.0.0"
+    version = "1.0.0",
+    persistent_state = true
 )]
 struct DataProcessorService {
-    // Your service state goes here
+    // Your service state goes here; `counter` is snapshotted to the KV store
+    // under the key "data/counter" so it survives a node restart
     counter: u32,
 }
 
@@ -64,14 +70,51 @@ impl DataProcessorService {
         // Simply return the data or an error
         Ok(transformed)
     }
+
+    /// A streaming variant of `transform` for batches too large to compute
+    /// up front and return in one `ServiceResponse`. Instead of a return
+    /// value, the handler receives a `ResponseSink` and pushes one chunk per
+    /// transformed item; the node delivers each `sink.send` to the caller's
+    /// `node.request_stream` as it happens, the same push model statsrv's
+    /// `sse` router uses.
+    #[action(name = "transform_batch", stream = true)]
+    async fn transform_batch(&self, context: &RequestContext, items: Vec<String>, sink: ResponseSink) -> Result<()> {
+        for item in items {
+            let transformed = item.to_uppercase();
+
+            let event_data = vmap! {
+                "source" => "transform_batch",
+                "data" => transformed.clone()
+            };
+            context.publish("events/data_events", event_data).await?;
+
+            sink.send(ValueType::String(transformed)).await?;
+        }
+
+        Ok(())
+    }
     
     /// Another action method that increments the counter
-    /// 
-    /// This example doesn't use the context parameter and only accesses service state.
+    ///
+    /// The counter is persisted under the "data/counter" key via the KV-backed
+    /// state subsystem, so this can't just do `self.counter += value`: another
+    /// concurrent invocation could read the same starting value. Instead it
+    /// reads the current value, computes the new one, and compare-and-swaps
+    /// it in, retrying on a mismatch (the Maelstrom seq-kv pattern) to get a
+    /// linearizable counter that also survives a node restart.
     #[action(name = "increment")]
     async fn increment_counter(&mut self, context: &RequestContext, value: u32) -> Result<u32> {
-        // Add the passed value to the counter
-        self.counter += value + 1;
+        let delta = value + 1;
+        let new_value = loop {
+            let current: u32 = context.kv_read("data/counter").await?.unwrap_or(0);
+            let candidate = current + delta;
+            match context.kv_cas("data/counter", Some(current), candidate, true).await {
+                Ok(true) => break candidate,
+                Ok(false) => continue, // lost the race to a concurrent invocation; retry
+                Err(e) => return Err(e),
+            }
+        };
+        self.counter = new_value;
 
         // Publish the counter value as an event
         let event_data = vmap! {
@@ -157,6 +200,23 @@ impl EventHandlerService {
         Ok(())
     }
 
+    /// Durably subscribe to the data service's events so nothing published
+    /// before this service was added - or while it was disconnected - is
+    /// lost. The node keeps a per-topic append-only log with monotonically
+    /// increasing sequence numbers; on (re)subscription it walks forward
+    /// from this subscriber's last-acknowledged sequence, redelivers every
+    /// event in between in order, and then switches to live delivery.
+    #[subscribe(topic = "events/data_events", durable = true, from = Checkpoint)]
+    async fn handle_data_events(&mut self, payload: ValueType) -> Result<()> {
+        // Use vmap! to get parameters with defaults
+        let source = vmap!(payload, "source" => String::new());
+        if !source.is_empty() {
+            println!("Received data event from: {}", source);
+            self.events_received.push(source);
+        }
+        Ok(())
+    }
+
     /// Handle custom events published directly via the node API
     // #[subscribe(topic = "events/custom")]
     #[subscribe]
@@ -224,7 +284,23 @@ async fn main() -> Result<()> {
     
     // Start the node
     node.start().await?;
-    
+
+    // Look for other nodes on the LAN the same way an SSDP/UPnP control point
+    // would: multicast an advertisement request, wait out the timeout for
+    // replies, and build the list from whichever nodes responded with a
+    // service/action manifest. Nothing else is running here, so this just
+    // demonstrates the call; on a real LAN it would return each peer's
+    // node id, endpoint and service paths.
+    let discovered = node.discover(Duration::from_millis(200)).await?;
+    println!("Discovered {} peer node(s)", discovered.len());
+
+    // Subscribe directly from calling code without registering a service
+    // handler. The wildcard "events/*" matches every data-service and
+    // event-handler topic below; the bounded channel backing the stream
+    // applies backpressure to slow consumers, and dropping `data_events`
+    // unregisters the internal listener automatically.
+    let mut data_events = node.subscribe("events/*").await?;
+
     // Transform a string using the data service
     let transform_result = node.request(
         "data/transform",
@@ -280,10 +356,39 @@ async fn main() -> Result<()> {
     // Using vmap! to extract values from the response
     let counter_value = vmap!(increment_result.data, => 0.0);
     assert_eq!(counter_value, 1.0);
-    
+
+    // Stream a batch transform instead of waiting for the whole batch and
+    // buffering it into one ServiceResponse
+    let mut transform_stream = node.request_stream(
+        "data/transform_batch",
+        vmap! {
+            "items" => vec!["alice", "bob", "carol"]
+        }
+    ).await?;
+
+    while let Some(chunk) = transform_stream.next().await {
+        println!("Transform batch chunk: {:?}", chunk?);
+    }
+
     // Wait a bit for events to be processed
     tokio::time::sleep(Duration::from_millis(100)).await;
-    
+
+    // Drain the events that the transform/transform/combine/increment/
+    // transform_batch actions above already published to "events/data_events"
+    // (4 from the singular actions - transform is called twice - plus one
+    // per item in transform_batch), read straight off the stream instead of
+    // through a registered subscriber.
+    for _ in 0..7 {
+        if let Some(event) = data_events.next().await {
+            let source = vmap!(event, "source" => String::new());
+            println!("Client-side stream saw event from: {}", source);
+        }
+    }
+
+    // Tear down the client-side subscription; dropping the stream unregisters
+    // the internal listener so the node stops buffering events for it.
+    drop(data_events);
+
     // Publish an event directly using the node API
     node.publish(
         "events/custom",
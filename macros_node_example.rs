@@ -6,61 +6,77 @@
  * 2. Define action handlers for operations
  * 3. Register event handlers for subscriptions
  * 4. Interact with services through the Node API
+ * 5. Declare per-operation parameter schemas in ServiceMetadata for automatic validation
  */
 
 use anyhow::Result;
 use runar_node::{
     services::{
-        AbstractService, RequestContext, ResponseStatus, ServiceResponse, 
-        ServiceState, ServiceMetadata, ValueType, ServiceRequest
+        AbstractService, OperationDescriptor, ParamSchema, ParamType, RequestContext,
+        ResponseStatus, ServiceResponse, ServiceState, ServiceMetadata, ValueType, ServiceRequest
     }
 };
 use std::sync::{Arc, Mutex};
 use tokio;
 use std::collections::HashMap;
 use async_trait::async_trait;
+use futures::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
 
-/// Example of a data processing service
-struct DataProcessorService {
+/// Shorthand for a required parameter with no extra constraints, covering
+/// the common case among this file's operation descriptors.
+fn required(name: &str, ty: ParamType) -> ParamSchema {
+    ParamSchema {
+        name: name.to_string(),
+        ty,
+        required: true,
+        max_len: None,
+        min: None,
+        max: None,
+        max_items: None,
+    }
+}
+
+/// State behind `DataProcessorService`, held behind a single `Arc` so cloning
+/// the service is a cheap pointer clone instead of cloning four fields by hand.
+struct DataProcessorServiceInner {
     name: String,
     path: String,
     description: String,
     version: String,
-    state: Arc<Mutex<ServiceState>>,
-    counter: Arc<Mutex<i32>>,
+    state: Mutex<ServiceState>,
+    counter: Mutex<i32>,
 }
 
-impl Clone for DataProcessorService {
-    fn clone(&self) -> Self {
-        Self {
-            name: self.name.clone(),
-            path: self.path.clone(),
-            description: self.description.clone(),
-            version: self.version.clone(),
-            state: Arc::clone(&self.state),
-            counter: Arc::clone(&self.counter),
-        }
-    }
-}
+/// Example of a data processing service
+#[derive(Clone)]
+struct DataProcessorService(Arc<DataProcessorServiceInner>);
 
 impl DataProcessorService {
     /// Create a new instance of the service
     pub fn new() -> Self {
-        Self {
+        Self(Arc::new(DataProcessorServiceInner {
             name: "data".to_string(),
             path: "/services/data".to_string(),
             description: "A service for processing data operations".to_string(),
             version: "1.0.0".to_string(),
-            state: Arc::new(Mutex::new(ServiceState::Created)),
-            counter: Arc::new(Mutex::new(0)),
-        }
+            state: Mutex::new(ServiceState::Created),
+            counter: Mutex::new(0),
+        }))
     }
     
     /// Transform a string to uppercase
+    ///
+    /// `input` is declared as a required String parameter in `metadata()`,
+    /// and a malformed request (e.g. `input: 123`) is still rejected
+    /// gracefully below rather than relying on that check alone.
     async fn transform_string(&self, ctx: &RequestContext) -> Result<ServiceResponse> {
         let input = match ctx.data.get("input") {
             Some(ValueType::String(s)) => s.clone(),
-            _ => return Ok(ServiceResponse::error("Missing input parameter".to_string())),
+            // The operation schema only guarantees presence/type for a
+            // well-behaved caller; reject gracefully rather than trusting it
+            // can never be wrong.
+            _ => return Ok(ServiceResponse::error("input must be a string")),
         };
 
         let result = format!("Transformed: {}", input.to_uppercase());
@@ -84,7 +100,7 @@ impl DataProcessorService {
     async fn increment_counter(&self, ctx: &RequestContext) -> Result<ServiceResponse> {
         // Increment counter and get its value without holding the lock across await points
         let value = {
-            let mut counter = self.counter.lock().unwrap();
+            let mut counter = self.0.counter.lock().unwrap();
             *counter += 1;
             *counter
         };
@@ -105,15 +121,22 @@ impl DataProcessorService {
     }
     
     /// Combine two strings
+    ///
+    /// `str1`/`str2` are declared as required String parameters in
+    /// `metadata()`, so the node rejects a request missing either before it
+    /// ever reaches here.
     async fn combine_strings(&self, ctx: &RequestContext) -> Result<ServiceResponse> {
         let str1 = match ctx.data.get("str1") {
             Some(ValueType::String(s)) => s.clone(),
-            _ => return Ok(ServiceResponse::error("Missing str1 parameter".to_string())),
+            // The operation schema only guarantees presence/type for a
+            // well-behaved caller; reject gracefully rather than trusting it
+            // can never be wrong.
+            _ => return Ok(ServiceResponse::error("str1 must be a string")),
         };
 
         let str2 = match ctx.data.get("str2") {
             Some(ValueType::String(s)) => s.clone(),
-            _ => return Ok(ServiceResponse::error("Missing str2 parameter".to_string())),
+            _ => return Ok(ServiceResponse::error("str2 must be a string")),
         };
 
         let result = format!("Combined: {} + {}", str1, str2);
@@ -130,184 +153,689 @@ impl DataProcessorService {
             Some(ValueType::Map(result_map)),
         ))
     }
+
+    /// A heavier transform that runs in the background instead of blocking
+    /// the request path. Enqueues the work via `ctx.spawn_job` and returns
+    /// immediately with a `JobId` the caller can poll via `jobs/status`.
+    async fn transform_async(&self, ctx: &RequestContext) -> Result<ServiceResponse> {
+        let input = match ctx.data.get("input") {
+            Some(ValueType::String(s)) => s.clone(),
+            // The operation schema only guarantees presence/type for a
+            // well-behaved caller; reject gracefully rather than trusting it
+            // can never be wrong.
+            _ => return Ok(ServiceResponse::error("input must be a string")),
+        };
+
+        let job_id = ctx.spawn_job(move || async move {
+            // Simulate a heavy transform that shouldn't block the request thread
+            tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+            Ok(ValueType::String(format!("Transformed: {}", input.to_uppercase())))
+        }).await?;
+
+        let mut result_map = HashMap::new();
+        result_map.insert("job_id".to_string(), ValueType::String(job_id.to_string()));
+
+        Ok(ServiceResponse::success(
+            "Transform job enqueued".to_string(),
+            Some(ValueType::Map(result_map)),
+        ))
+    }
 }
 
 #[async_trait]
 impl AbstractService for DataProcessorService {
     fn name(&self) -> &str {
-        &self.name
+        &self.0.name
     }
-    
+
     fn path(&self) -> &str {
-        &self.path
+        &self.0.path
     }
-    
+
     fn state(&self) -> ServiceState {
-        *self.state.lock().unwrap()
+        *self.0.state.lock().unwrap()
     }
-    
+
     fn description(&self) -> &str {
-        &self.description
+        &self.0.description
     }
-    
+
     fn metadata(&self) -> ServiceMetadata {
         ServiceMetadata {
-            name: self.name.clone(),
-            path: self.path.clone(),
-            description: self.description.clone(),
-            version: self.version.clone(),
+            name: self.0.name.clone(),
+            path: self.0.path.clone(),
+            description: self.0.description.clone(),
+            version: self.0.version.clone(),
             state: self.state(),
-            operations: vec!["transform".to_string(), "increment".to_string(), "combine".to_string()],
+            operations: vec![
+                OperationDescriptor {
+                    name: "transform".to_string(),
+                    params: vec![required("input", ParamType::String)],
+                },
+                OperationDescriptor {
+                    name: "increment".to_string(),
+                    params: vec![],
+                },
+                OperationDescriptor {
+                    name: "combine".to_string(),
+                    params: vec![
+                        required("str1", ParamType::String),
+                        required("str2", ParamType::String),
+                    ],
+                },
+                OperationDescriptor {
+                    name: "transform_async".to_string(),
+                    params: vec![required("input", ParamType::String)],
+                },
+            ],
         }
     }
     
     // Method signatures must match exactly with AbstractService trait
     async fn init(&mut self, _ctx: &RequestContext) -> Result<()> {
-        let mut state = self.state.lock().unwrap();
+        let mut state = self.0.state.lock().unwrap();
         *state = ServiceState::Initialized;
         Ok(())
     }
-    
+
     async fn start(&mut self) -> Result<()> {
-        let mut state = self.state.lock().unwrap();
+        let mut state = self.0.state.lock().unwrap();
         *state = ServiceState::Running;
         Ok(())
     }
-    
+
     async fn stop(&mut self) -> Result<()> {
-        let mut state = self.state.lock().unwrap();
+        let mut state = self.0.state.lock().unwrap();
         *state = ServiceState::Stopped;
         Ok(())
     }
-    
-    async fn handle_request(&self, request: ServiceRequest) -> Result<ServiceResponse> {
-        // Print request for debugging
-        println!("Data Processor received request: operation={}, params={:?}", 
-                 request.operation, request.params);
-        
-        // Extract parameters from the request
-        let data_map = match &request.request_context.data {
-            ValueType::Map(map) => {
-                let mut new_map = map.clone();
-                
-                // Add parameters to the map if they exist
-                if let Some(ValueType::Map(param_map)) = &request.params {
-                    for (key, value) in param_map {
-                        new_map.insert(key.clone(), value.clone());
-                    }
-                }
-                
-                new_map
+
+    // handle_request is provided by AbstractService's default impl, which
+    // merges request.params into the context's data map (the ValueType::Map
+    // flattening every service used to duplicate by hand) and dispatches
+    // here by operation name.
+    async fn handle_action(&self, op: &str, ctx: &RequestContext) -> Result<ServiceResponse> {
+        println!("Data Processor received request: operation={}", op);
+
+        match op {
+            "transform" => self.transform_string(ctx).await,
+            "increment" => self.increment_counter(ctx).await,
+            "combine" => self.combine_strings(ctx).await,
+            "transform_async" => self.transform_async(ctx).await,
+            _ => Ok(ServiceResponse::error(format!("Unknown operation: {}", op)))
+        }
+    }
+}
+
+/// A single component of an operational-transform edit sequence, matching the
+/// classic OT op model: retain `n` characters unchanged, insert a string, or
+/// delete `n` characters.
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Retain(usize),
+    Insert(String),
+    Delete(usize),
+}
+
+impl Op {
+    /// Parses an op out of its wire representation, a map with a "type"
+    /// field ("retain" | "insert" | "delete") and a matching "value".
+    fn from_value(value: &ValueType) -> Result<Self> {
+        let map = match value {
+            ValueType::Map(map) => map,
+            _ => return Err(anyhow::anyhow!("op must be a map")),
+        };
+
+        match map.get("type") {
+            Some(ValueType::String(t)) if t == "retain" => match map.get("value") {
+                Some(ValueType::Number(n)) => Ok(Op::Retain(*n as usize)),
+                _ => Err(anyhow::anyhow!("retain op missing numeric value")),
             },
-            _ => {
-                let mut new_map = HashMap::new();
-                
-                // Add parameters to the map if they exist
-                if let Some(ValueType::Map(param_map)) = &request.params {
-                    for (key, value) in param_map {
-                        new_map.insert(key.clone(), value.clone());
-                    }
-                }
-                
-                new_map
+            Some(ValueType::String(t)) if t == "insert" => match map.get("value") {
+                Some(ValueType::String(s)) => Ok(Op::Insert(s.clone())),
+                _ => Err(anyhow::anyhow!("insert op missing string value")),
+            },
+            Some(ValueType::String(t)) if t == "delete" => match map.get("value") {
+                Some(ValueType::Number(n)) => Ok(Op::Delete(*n as usize)),
+                _ => Err(anyhow::anyhow!("delete op missing numeric value")),
+            },
+            _ => Err(anyhow::anyhow!("op has an unknown or missing type")),
+        }
+    }
+
+    fn to_value(&self) -> ValueType {
+        let mut map = HashMap::new();
+        match self {
+            Op::Retain(n) => {
+                map.insert("type".to_string(), ValueType::String("retain".to_string()));
+                map.insert("value".to_string(), ValueType::Number(*n as f64));
+            },
+            Op::Insert(s) => {
+                map.insert("type".to_string(), ValueType::String("insert".to_string()));
+                map.insert("value".to_string(), ValueType::String(s.clone()));
+            },
+            Op::Delete(n) => {
+                map.insert("type".to_string(), ValueType::String("delete".to_string()));
+                map.insert("value".to_string(), ValueType::Number(*n as f64));
+            },
+        }
+        ValueType::Map(map)
+    }
+}
+
+/// Applies an op sequence to a document, returning the resulting text.
+///
+/// `n` on a `Retain`/`Delete` op is caller-controlled (it comes straight off
+/// the wire in `apply_op`) and isn't bounded by the operation schema, so it
+/// has to be checked against what's left of the document here rather than
+/// indexed into blindly.
+fn apply(doc: &str, ops: &[Op]) -> Result<String> {
+    let chars: Vec<char> = doc.chars().collect();
+    let mut pos = 0;
+    let mut result = String::new();
+
+    for op in ops {
+        match op {
+            Op::Retain(n) => {
+                let end = pos.checked_add(*n).filter(|&end| end <= chars.len())
+                    .ok_or_else(|| anyhow::anyhow!("retain op runs past the end of the document"))?;
+                result.extend(&chars[pos..end]);
+                pos = end;
+            },
+            Op::Insert(s) => result.push_str(s),
+            Op::Delete(n) => {
+                pos = pos.checked_add(*n).filter(|&end| end <= chars.len())
+                    .ok_or_else(|| anyhow::anyhow!("delete op runs past the end of the document"))?;
+            },
+        }
+    }
+
+    Ok(result)
+}
+
+/// Transforms `a` against `b`, both generated against the same base document,
+/// returning `(a', b')` such that `apply(apply(doc, a), b') == apply(apply(doc, b), a')`.
+///
+/// Walks both op sequences in lockstep over the same base length: retains
+/// advance together, inserts from one side are pushed through while the other
+/// side gets a matching retain, and overlapping deletes/retains are split at
+/// the shorter of the two runs.
+fn transform(a: &[Op], b: &[Op]) -> (Vec<Op>, Vec<Op>) {
+    let mut a_prime = Vec::new();
+    let mut b_prime = Vec::new();
+
+    let mut a_ops = a.iter().cloned();
+    let mut b_ops = b.iter().cloned();
+    let mut a_op = a_ops.next();
+    let mut b_op = b_ops.next();
+
+    loop {
+        match (&a_op, &b_op) {
+            (None, None) => break,
+            (Some(Op::Insert(s)), _) => {
+                a_prime.push(Op::Insert(s.clone()));
+                b_prime.push(Op::Retain(s.chars().count()));
+                a_op = a_ops.next();
+            },
+            (_, Some(Op::Insert(s))) => {
+                a_prime.push(Op::Retain(s.chars().count()));
+                b_prime.push(Op::Insert(s.clone()));
+                b_op = b_ops.next();
+            },
+            (Some(Op::Retain(ra)), Some(Op::Retain(rb))) => {
+                let n = (*ra).min(*rb);
+                a_prime.push(Op::Retain(n));
+                b_prime.push(Op::Retain(n));
+                a_op = advance(Op::Retain(*ra), n, &mut a_ops, Op::Retain);
+                b_op = advance(Op::Retain(*rb), n, &mut b_ops, Op::Retain);
+            },
+            (Some(Op::Delete(da)), Some(Op::Delete(db))) => {
+                let n = (*da).min(*db);
+                // Both sides already agree this span is gone; neither op' needs to say it again
+                a_op = advance(Op::Delete(*da), n, &mut a_ops, Op::Delete);
+                b_op = advance(Op::Delete(*db), n, &mut b_ops, Op::Delete);
+            },
+            (Some(Op::Delete(da)), Some(Op::Retain(rb))) => {
+                let n = (*da).min(*rb);
+                a_prime.push(Op::Delete(n));
+                a_op = advance(Op::Delete(*da), n, &mut a_ops, Op::Delete);
+                b_op = advance(Op::Retain(*rb), n, &mut b_ops, Op::Retain);
+            },
+            (Some(Op::Retain(ra)), Some(Op::Delete(db))) => {
+                let n = (*ra).min(*db);
+                b_prime.push(Op::Delete(n));
+                a_op = advance(Op::Retain(*ra), n, &mut a_ops, Op::Retain);
+                b_op = advance(Op::Delete(*db), n, &mut b_ops, Op::Delete);
+            },
+            (Some(_), None) => {
+                a_prime.push(a_op.take().unwrap());
+                a_op = a_ops.next();
+            },
+            (None, Some(_)) => {
+                b_prime.push(b_op.take().unwrap());
+                b_op = b_ops.next();
+            },
+        }
+    }
+
+    (a_prime, b_prime)
+}
+
+/// Consumes `n` units of a retain/delete run, returning either the
+/// leftover run (if the other side's run was shorter) or the next op.
+fn advance(
+    op: Op,
+    n: usize,
+    rest: &mut impl Iterator<Item = Op>,
+    make: impl Fn(usize) -> Op,
+) -> Option<Op> {
+    let remaining = match op {
+        Op::Retain(run) | Op::Delete(run) => run - n,
+        Op::Insert(_) => unreachable!("advance is only used for retain/delete runs"),
+    };
+
+    if remaining > 0 {
+        Some(make(remaining))
+    } else {
+        rest.next()
+    }
+}
+
+// Document text and commit history behind a single lock so a read-transform-
+// apply-push sequence in `apply_op` is one atomic critical section: two
+// concurrent calls can never both transform against the same snapshot of
+// `history` before either commits.
+struct BufferState {
+    document: String,
+    history: Vec<Vec<Op>>,
+}
+
+/// State behind `CollaborativeBufferService`, held behind a single `Arc` so
+/// cloning the service is a cheap pointer clone instead of cloning five
+/// fields by hand.
+struct CollaborativeBufferServiceInner {
+    name: String,
+    path: String,
+    description: String,
+    version: String,
+    state: Mutex<ServiceState>,
+    buffer: Mutex<BufferState>,
+}
+
+/// A collaborative text-buffer service demonstrating real concurrency-safe
+/// editing through operational transform, rather than the single-writer
+/// string mutations in `DataProcessorService::transform_string`.
+///
+/// Clients submit an op sequence against a `base_revision`; if other edits
+/// have committed since then, the incoming op is transformed against every
+/// committed op in between before being applied, so concurrent edits never
+/// clobber each other.
+#[derive(Clone)]
+struct CollaborativeBufferService(Arc<CollaborativeBufferServiceInner>);
+
+impl CollaborativeBufferService {
+    /// Create a new instance of the service
+    pub fn new() -> Self {
+        Self(Arc::new(CollaborativeBufferServiceInner {
+            name: "buffer".to_string(),
+            path: "/services/buffer".to_string(),
+            description: "A collaboratively-edited text buffer using operational transform".to_string(),
+            version: "1.0.0".to_string(),
+            state: Mutex::new(ServiceState::Created),
+            buffer: Mutex::new(BufferState {
+                document: String::new(),
+                history: Vec::new(),
+            }),
+        }))
+    }
+
+    /// Applies a client's op sequence, transforming it against any ops
+    /// committed since `base_revision`, and publishes the transformed op
+    /// plus the new revision on "buffer_event" for other subscribers to replay.
+    ///
+    /// `base_revision`/`ops` are declared as required parameters in
+    /// `metadata()`, which validates their presence, type, and the op count
+    /// against `max_items`; a malformed request is still rejected gracefully
+    /// below instead of assumed away.
+    async fn apply_op(&self, ctx: &RequestContext) -> Result<ServiceResponse> {
+        let base_revision = match ctx.data.get("base_revision") {
+            Some(ValueType::Number(n)) => *n as usize,
+            // The operation schema only guarantees presence/type for a
+            // well-behaved caller; reject gracefully rather than trusting it
+            // can never be wrong.
+            _ => return Ok(ServiceResponse::error("base_revision must be a number")),
+        };
+
+        let raw_ops = match ctx.data.get("ops") {
+            Some(ValueType::Array(ops)) => ops,
+            _ => return Ok(ServiceResponse::error("ops must be an array")),
+        };
+
+        let mut incoming = Vec::with_capacity(raw_ops.len());
+        for op in raw_ops {
+            incoming.push(Op::from_value(op)?);
+        }
+
+        // Read the committed history, transform against it, apply to the
+        // document, and push the new op onto history all under one lock
+        // acquisition so no other `apply_op` call can interleave between the
+        // transform and the commit.
+        let (document, transformed, new_revision) = {
+            let mut buffer = self.0.buffer.lock().unwrap();
+
+            let mut transformed = incoming;
+            for committed in buffer.history.iter().skip(base_revision) {
+                let (a_prime, _) = transform(&transformed, committed);
+                transformed = a_prime;
             }
+
+            buffer.document = apply(&buffer.document, &transformed)?;
+            buffer.history.push(transformed.clone());
+
+            (buffer.document.clone(), transformed, buffer.history.len())
         };
-        
-        // Create a new context with the updated data map
-        let new_context = RequestContext::new(
-            request.request_context.path.clone(),
-            ValueType::Map(data_map),
-            request.request_context.node_handler.clone()
-        );
-        
-        match request.operation.as_str() {
-            "transform" => self.transform_string(&new_context).await,
-            "increment" => self.increment_counter(&new_context).await,
-            "combine" => self.combine_strings(&new_context).await,
-            _ => Ok(ServiceResponse::error(format!("Unknown operation: {}", request.operation)))
+
+        let ops_value = ValueType::Array(transformed.iter().map(Op::to_value).collect());
+        let mut event_data = HashMap::new();
+        event_data.insert("ops".to_string(), ops_value);
+        event_data.insert("revision".to_string(), ValueType::Number(new_revision as f64));
+        ctx.publish("buffer_event", ValueType::Map(event_data)).await?;
+
+        let mut result_map = HashMap::new();
+        result_map.insert("revision".to_string(), ValueType::Number(new_revision as f64));
+        result_map.insert("document".to_string(), ValueType::String(document));
+
+        Ok(ServiceResponse::success(
+            "Op applied successfully".to_string(),
+            Some(ValueType::Map(result_map)),
+        ))
+    }
+
+    /// Action to retrieve the current document and revision
+    async fn get_document(&self, _ctx: &RequestContext) -> Result<ServiceResponse> {
+        let buffer = self.0.buffer.lock().unwrap();
+        let document = buffer.document.clone();
+        let revision = buffer.history.len();
+        drop(buffer);
+
+        let mut result_map = HashMap::new();
+        result_map.insert("document".to_string(), ValueType::String(document));
+        result_map.insert("revision".to_string(), ValueType::Number(revision as f64));
+
+        Ok(ServiceResponse::success(
+            "Document retrieved successfully".to_string(),
+            Some(ValueType::Map(result_map)),
+        ))
+    }
+}
+
+#[async_trait]
+impl AbstractService for CollaborativeBufferService {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn path(&self) -> &str {
+        &self.0.path
+    }
+
+    fn state(&self) -> ServiceState {
+        *self.0.state.lock().unwrap()
+    }
+
+    fn description(&self) -> &str {
+        &self.0.description
+    }
+
+    fn metadata(&self) -> ServiceMetadata {
+        ServiceMetadata {
+            name: self.0.name.clone(),
+            path: self.0.path.clone(),
+            description: self.0.description.clone(),
+            version: self.0.version.clone(),
+            state: self.state(),
+            operations: vec![
+                OperationDescriptor {
+                    name: "apply_op".to_string(),
+                    params: vec![
+                        ParamSchema {
+                            name: "base_revision".to_string(),
+                            ty: ParamType::Number,
+                            required: true,
+                            max_len: None,
+                            min: Some(0.0),
+                            max: None,
+                            max_items: None,
+                        },
+                        ParamSchema {
+                            name: "ops".to_string(),
+                            ty: ParamType::Array,
+                            required: true,
+                            max_len: None,
+                            min: None,
+                            max: None,
+                            max_items: Some(1000),
+                        },
+                    ],
+                },
+                OperationDescriptor {
+                    name: "get_document".to_string(),
+                    params: vec![],
+                },
+            ],
+        }
+    }
+
+    async fn init(&mut self, _ctx: &RequestContext) -> Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        *state = ServiceState::Initialized;
+        Ok(())
+    }
+
+    async fn start(&mut self) -> Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        *state = ServiceState::Running;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        *state = ServiceState::Stopped;
+        Ok(())
+    }
+
+    // handle_request is provided by AbstractService's default impl, which
+    // merges request.params into the context's data map (the ValueType::Map
+    // flattening this service used to duplicate by hand) and dispatches
+    // here by operation name.
+    async fn handle_action(&self, op: &str, ctx: &RequestContext) -> Result<ServiceResponse> {
+        println!("Collaborative Buffer received request: operation={}", op);
+
+        match op {
+            "apply_op" => self.apply_op(ctx).await,
+            "get_document" => self.get_document(ctx).await,
+            _ => Ok(ServiceResponse::error(format!("Unknown operation: {}", op)))
         }
     }
 }
 
-/// Example of an event handler service
-struct EventHandlerService {
+/// State behind `JobsService`, held behind a single `Arc` so cloning the
+/// service is a cheap pointer clone instead of cloning four fields by hand.
+struct JobsServiceInner {
     name: String,
     path: String,
     description: String,
     version: String,
-    state: Arc<Mutex<ServiceState>>,
-    received_events: Arc<Mutex<Vec<String>>>,
+    state: Mutex<ServiceState>,
 }
 
-impl Clone for EventHandlerService {
-    fn clone(&self) -> Self {
-        Self {
-            name: self.name.clone(),
-            path: self.path.clone(),
-            description: self.description.clone(),
-            version: self.version.clone(),
-            state: Arc::clone(&self.state),
-            received_events: Arc::clone(&self.received_events),
+/// A thin built-in service that lets callers poll the node's background job
+/// registry instead of every service re-implementing its own polling
+/// endpoint. Jobs are enqueued elsewhere (e.g. `DataProcessorService::transform_async`
+/// via `ctx.spawn_job`) and tracked by the node as
+/// `Pending | Running | Done(ValueType) | Failed(String)`.
+#[derive(Clone)]
+struct JobsService(Arc<JobsServiceInner>);
+
+impl JobsService {
+    /// Create a new instance of the service
+    pub fn new() -> Self {
+        Self(Arc::new(JobsServiceInner {
+            name: "jobs".to_string(),
+            path: "/services/jobs".to_string(),
+            description: "Polls the status of background jobs".to_string(),
+            version: "1.0.0".to_string(),
+            state: Mutex::new(ServiceState::Created),
+        }))
+    }
+
+    /// Looks up a job's current status by id
+    ///
+    /// `job_id` is declared as a required String parameter in `metadata()`,
+    /// and a malformed request is still rejected gracefully below rather
+    /// than relying on that check alone.
+    async fn status(&self, ctx: &RequestContext) -> Result<ServiceResponse> {
+        let job_id = match ctx.data.get("job_id") {
+            Some(ValueType::String(s)) => s.clone(),
+            // The operation schema only guarantees presence/type for a
+            // well-behaved caller; reject gracefully rather than trusting it
+            // can never be wrong.
+            _ => return Ok(ServiceResponse::error("job_id must be a string")),
+        };
+
+        let status = ctx.job_status(&job_id).await?;
+
+        let mut result_map = HashMap::new();
+        result_map.insert("job_id".to_string(), ValueType::String(job_id));
+        result_map.insert("status".to_string(), ValueType::String(status));
+
+        Ok(ServiceResponse::success(
+            "Job status retrieved successfully".to_string(),
+            Some(ValueType::Map(result_map)),
+        ))
+    }
+}
+
+#[async_trait]
+impl AbstractService for JobsService {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn path(&self) -> &str {
+        &self.0.path
+    }
+
+    fn state(&self) -> ServiceState {
+        *self.0.state.lock().unwrap()
+    }
+
+    fn description(&self) -> &str {
+        &self.0.description
+    }
+
+    fn metadata(&self) -> ServiceMetadata {
+        ServiceMetadata {
+            name: self.0.name.clone(),
+            path: self.0.path.clone(),
+            description: self.0.description.clone(),
+            version: self.0.version.clone(),
+            state: self.state(),
+            operations: vec![OperationDescriptor {
+                name: "status".to_string(),
+                params: vec![required("job_id", ParamType::String)],
+            }],
+        }
+    }
+
+    async fn init(&mut self, _ctx: &RequestContext) -> Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        *state = ServiceState::Initialized;
+        Ok(())
+    }
+
+    async fn start(&mut self) -> Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        *state = ServiceState::Running;
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        let mut state = self.0.state.lock().unwrap();
+        *state = ServiceState::Stopped;
+        Ok(())
+    }
+
+    // handle_request is provided by AbstractService's default impl, which
+    // merges request.params into the context's data map (the ValueType::Map
+    // flattening this service used to duplicate by hand) and dispatches
+    // here by operation name.
+    async fn handle_action(&self, op: &str, ctx: &RequestContext) -> Result<ServiceResponse> {
+        match op {
+            "status" => self.status(ctx).await,
+            _ => Ok(ServiceResponse::error(format!("Unknown operation: {}", op)))
         }
     }
 }
 
+struct EventHandlerServiceInner {
+    name: String,
+    path: String,
+    description: String,
+    version: String,
+    state: Mutex<ServiceState>,
+    received_events: Mutex<Vec<String>>,
+}
+
+/// Example of an event handler service
+#[derive(Clone)]
+struct EventHandlerService(Arc<EventHandlerServiceInner>);
+
 impl EventHandlerService {
     /// Create a new instance of the service
     pub fn new() -> Self {
-        Self {
+        Self(Arc::new(EventHandlerServiceInner {
             name: "events".to_string(),
             path: "/services/events".to_string(),
             description: "A service for handling various events".to_string(),
             version: "1.0.0".to_string(),
-            state: Arc::new(Mutex::new(ServiceState::Created)),
-            received_events: Arc::new(Mutex::new(Vec::new())),
-        }
+            state: Mutex::new(ServiceState::Created),
+            received_events: Mutex::new(Vec::new()),
+        }))
     }
-    
+
     async fn handle_text_event(&self, data: ValueType) -> Result<()> {
         if let ValueType::Map(map) = data {
             if let Some(ValueType::String(text)) = map.get("text") {
                 let event_text = format!("Received text event: {}", text);
-                self.received_events.lock().unwrap().push(event_text);
+                self.0.received_events.lock().unwrap().push(event_text);
             }
         }
-        
-        Ok(())
-    }
-    
-    async fn handle_math_event(&self, data: ValueType) -> Result<()> {
-        if let ValueType::Map(map) = data {
-            if let Some(ValueType::Number(value)) = map.get("value") {
-                let event_text = format!("Received math event with value: {}", value);
-                self.received_events.lock().unwrap().push(event_text);
-            }
-        }
-        
+
         Ok(())
     }
-    
+
     async fn handle_custom_event(&self, data: ValueType) -> Result<()> {
         let event_text = format!("Received custom event: {:?}", data);
-        self.received_events.lock().unwrap().push(event_text);
-        
+        self.0.received_events.lock().unwrap().push(event_text);
+
         Ok(())
     }
-    
+
     /// Action to retrieve received events
     async fn get_events(&self, _ctx: &RequestContext) -> Result<ServiceResponse> {
         // Get events without holding the lock across await points
-        let events = self.received_events.lock().unwrap().clone();
-        
+        let events = self.0.received_events.lock().unwrap().clone();
+
         let events_value: Vec<ValueType> = events
             .into_iter()
             .map(ValueType::String)
             .collect();
-        
+
         // Create a map with the events for the response
         let mut result_map = HashMap::new();
         result_map.insert("events".to_string(), ValueType::Array(events_value));
-        
+
         Ok(ServiceResponse::success(
             "Events retrieved successfully".to_string(),
             Some(ValueType::Map(result_map)),
@@ -318,29 +846,38 @@ impl EventHandlerService {
 #[async_trait]
 impl AbstractService for EventHandlerService {
     fn name(&self) -> &str {
-        &self.name
+        &self.0.name
     }
-    
+
     fn path(&self) -> &str {
-        &self.path
+        &self.0.path
     }
-    
+
     fn state(&self) -> ServiceState {
-        *self.state.lock().unwrap()
+        *self.0.state.lock().unwrap()
     }
-    
+
     fn description(&self) -> &str {
-        &self.description
+        &self.0.description
     }
-    
+
     fn metadata(&self) -> ServiceMetadata {
         ServiceMetadata {
-            name: self.name.clone(),
-            path: self.path.clone(),
-            description: self.description.clone(),
-            version: self.version.clone(),
+            name: self.0.name.clone(),
+            path: self.0.path.clone(),
+            description: self.0.description.clone(),
+            version: self.0.version.clone(),
             state: self.state(),
-            operations: vec!["get_events".to_string()],
+            operations: vec![
+                OperationDescriptor {
+                    name: "get_events".to_string(),
+                    params: vec![],
+                },
+                OperationDescriptor {
+                    name: "tail_math_events".to_string(),
+                    params: vec![],
+                },
+            ],
         }
     }
     
@@ -348,105 +885,108 @@ impl AbstractService for EventHandlerService {
     async fn init(&mut self, ctx: &RequestContext) -> Result<()> {
         // Set state without holding lock across await points
         {
-            let mut state = self.state.lock().unwrap();
+            let mut state = self.0.state.lock().unwrap();
             *state = ServiceState::Initialized;
         }
-        
+
         // Create references to self for event handlers
         let this = Arc::new(self.clone());
-        
-        // Subscribe to events using closures
+
+        // Subscribe to each topic as an owned async stream instead of a
+        // callback closure, and tail it from a single long-lived task per
+        // topic instead of spawning a fresh task per received event. The
+        // stream's channel is cleaned up automatically when it's dropped
+        // (here, when the task below exits).
         let this_clone = Arc::clone(&this);
-        ctx.subscribe("text_event", move |data| {
-            let this = this_clone.clone();
-            tokio::spawn(async move {
-                if let Err(e) = this.handle_text_event(data).await {
+        let mut text_events = ctx.subscribe_stream("text_event").await?;
+        tokio::spawn(async move {
+            while let Some(data) = text_events.next().await {
+                if let Err(e) = this_clone.handle_text_event(data).await {
                     eprintln!("Error handling text event: {}", e);
                 }
-            });
-            Ok(())
-        }).await?;
-        
+            }
+        });
+
+        // Instead of hard-coding the "math_event" topic, register a dataspace
+        // pattern: "every published map that has a `value` field", regardless
+        // of which topic it was asserted on. The matcher structurally unifies
+        // the pattern against each published value and only delivers the
+        // bound captures when every concrete field matches; `?value` binds any
+        // type, so the task below keeps only the numeric ones.
         let this_clone = Arc::clone(&this);
-        ctx.subscribe("math_event", move |data| {
-            let this = this_clone.clone();
-            tokio::spawn(async move {
-                if let Err(e) = this.handle_math_event(data).await {
-                    eprintln!("Error handling math event: {}", e);
+        let value_pattern = ValueType::Map({
+            let mut pattern = HashMap::new();
+            pattern.insert("value".to_string(), ValueType::String("?value".to_string()));
+            pattern
+        });
+        let mut numeric_value_events = ctx.subscribe_pattern(value_pattern).await?;
+        tokio::spawn(async move {
+            while let Some(bindings) = numeric_value_events.next().await {
+                if let Some(ValueType::Number(value)) = bindings.get("value") {
+                    let event_text = format!("Received math event with value: {}", value);
+                    this_clone.0.received_events.lock().unwrap().push(event_text);
                 }
-            });
-            Ok(())
-        }).await?;
-        
+            }
+        });
+
         let this_clone = Arc::clone(&this);
-        ctx.subscribe("custom_event", move |data| {
-            let this = this_clone.clone();
-            tokio::spawn(async move {
-                if let Err(e) = this.handle_custom_event(data).await {
+        let mut custom_events = ctx.subscribe_stream("custom_event").await?;
+        tokio::spawn(async move {
+            while let Some(data) = custom_events.next().await {
+                if let Err(e) = this_clone.handle_custom_event(data).await {
                     eprintln!("Error handling custom event: {}", e);
                 }
-            });
-            Ok(())
-        }).await?;
-        
+            }
+        });
+
         Ok(())
     }
     
     async fn start(&mut self) -> Result<()> {
-        let mut state = self.state.lock().unwrap();
+        let mut state = self.0.state.lock().unwrap();
         *state = ServiceState::Running;
         Ok(())
     }
-    
+
     async fn stop(&mut self) -> Result<()> {
-        let mut state = self.state.lock().unwrap();
+        let mut state = self.0.state.lock().unwrap();
         *state = ServiceState::Stopped;
         Ok(())
     }
-    
-    async fn handle_request(&self, request: ServiceRequest) -> Result<ServiceResponse> {
-        // Print request for debugging
-        println!("Event Handler received request: operation={}, params={:?}", 
-                 request.operation, request.params);
-        
-        // Extract parameters from the request
-        let data_map = match &request.request_context.data {
-            ValueType::Map(map) => {
-                let mut new_map = map.clone();
-                
-                // Add parameters to the map if they exist
-                if let Some(ValueType::Map(param_map)) = &request.params {
-                    for (key, value) in param_map {
-                        new_map.insert(key.clone(), value.clone());
+
+    async fn handle_action(&self, op: &str, ctx: &RequestContext) -> Result<ServiceResponse> {
+        println!("Event Handler received request: operation={}", op);
+
+        match op {
+            "get_events" => self.get_events(ctx).await,
+            _ => Ok(ServiceResponse::error(format!("Unknown operation: {}", op))),
+        }
+    }
+
+    /// Server-push action variant: instead of returning a single map,
+    /// "tail_math_events" hands back a long-lived stream that forwards every
+    /// "math_event" as it's published, so a client can live-tail the topic
+    /// through the request API instead of registering its own subscription.
+    async fn handle_request_stream(
+        &self,
+        request: ServiceRequest,
+    ) -> Result<ReceiverStream<Result<ValueType>>> {
+        match request.operation.as_str() {
+            "tail_math_events" => {
+                let mut math_events = request.request_context.subscribe_stream("math_event").await?;
+                let (tx, rx) = tokio::sync::mpsc::channel(16);
+
+                tokio::spawn(async move {
+                    while let Some(event) = math_events.next().await {
+                        if tx.send(Ok(event)).await.is_err() {
+                            break;
+                        }
                     }
-                }
-                
-                new_map
+                });
+
+                Ok(ReceiverStream::new(rx))
             },
-            _ => {
-                let mut new_map = HashMap::new();
-                
-                // Add parameters to the map if they exist
-                if let Some(ValueType::Map(param_map)) = &request.params {
-                    for (key, value) in param_map {
-                        new_map.insert(key.clone(), value.clone());
-                    }
-                }
-                
-                new_map
-            }
-        };
-        
-        // Create a new context with the updated data map
-        let new_context = RequestContext::new(
-            request.request_context.path.clone(),
-            ValueType::Map(data_map),
-            request.request_context.node_handler.clone()
-        );
-        
-        match request.operation.as_str() {
-            "get_events" => self.get_events(&new_context).await,
-            _ => Ok(ServiceResponse::error(format!("Unknown operation: {}", request.operation)))
+            _ => AbstractService::handle_request_stream(self, request).await,
         }
     }
 }
@@ -472,8 +1012,12 @@ async fn main() -> Result<()> {
         test_network_ids: None,
         bootstrap_nodes: None,
         listen_addr: None,
+        // Bound the background worker pool so heavy jobs enqueued via
+        // ctx.spawn_job (e.g. DataProcessorService::transform_async) can't
+        // run unbounded in parallel and starve the node
+        job_worker_concurrency: 4,
     };
-    
+
     // Create the node
     let mut node = runar_node::node::Node::new(node_config).await?;
     
@@ -483,16 +1027,22 @@ async fn main() -> Result<()> {
     // Create our services
     let mut data_service = DataProcessorService::new();
     let mut event_service = EventHandlerService::new();
-    
+    let mut buffer_service = CollaborativeBufferService::new();
+    let mut jobs_service = JobsService::new();
+
     // Initialize services
     let context = node.create_request_context("init").await?;
-    
+
     data_service.init(&context).await?;
     event_service.init(&context).await?;
-    
+    buffer_service.init(&context).await?;
+    jobs_service.init(&context).await?;
+
     // Register services with the node using the proper add_service method
     node.add_service(data_service).await?;
     node.add_service(event_service).await?;
+    node.add_service(buffer_service).await?;
+    node.add_service(jobs_service).await?;
     
     // Start the services
     node.start_services().await?;
@@ -619,7 +1169,93 @@ async fn main() -> Result<()> {
     } else {
         println!("Error: {}", events_result.message);
     }
-    
+
+    // 5. Collaboratively edit the shared buffer
+    println!("\nTesting collaborative buffer editing:");
+
+    // Two clients both start from revision 0 (an empty document) and submit
+    // concurrent edits; the second one committed must be transformed against
+    // the first so neither client's insert is lost.
+    let client_a_result = node.request(
+        "buffer/apply_op".to_string(),
+        ValueType::Map({
+            let mut map = HashMap::new();
+            map.insert("base_revision".to_string(), ValueType::Number(0.0));
+            map.insert("ops".to_string(), ValueType::Array(vec![
+                Op::Insert("Hello".to_string()).to_value(),
+            ]));
+            map
+        })
+    ).await?;
+    println!("Client A commit: {}", client_a_result.message);
+
+    let client_b_result = node.request(
+        "buffer/apply_op".to_string(),
+        ValueType::Map({
+            let mut map = HashMap::new();
+            map.insert("base_revision".to_string(), ValueType::Number(0.0));
+            map.insert("ops".to_string(), ValueType::Array(vec![
+                Op::Insert("World".to_string()).to_value(),
+            ]));
+            map
+        })
+    ).await?;
+    println!("Client B commit: {}", client_b_result.message);
+
+    let document_result = node.request(
+        "buffer/get_document".to_string(),
+        ValueType::Map(HashMap::new())
+    ).await?;
+
+    if let Some(ValueType::Map(map)) = &document_result.data {
+        if let Some(ValueType::String(doc)) = map.get("document") {
+            println!("Buffer document after both edits: {:?}", doc);
+        }
+    }
+
+    // 6. Enqueue a background job and poll its status
+    println!("\nTesting background job enqueue and polling:");
+    let enqueue_result = node.request(
+        "data/transform_async".to_string(),
+        ValueType::Map({
+            let mut map = HashMap::new();
+            map.insert("input".to_string(), ValueType::String("background hello".to_string()));
+            map
+        })
+    ).await?;
+
+    let job_id = match &enqueue_result.data {
+        Some(ValueType::Map(map)) => match map.get("job_id") {
+            Some(ValueType::String(id)) => id.clone(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    };
+    println!("Enqueued job: {}", job_id);
+
+    // Poll until the job completes; a real client might subscribe instead
+    loop {
+        let status_result = node.request(
+            "jobs/status".to_string(),
+            ValueType::Map({
+                let mut map = HashMap::new();
+                map.insert("job_id".to_string(), ValueType::String(job_id.clone()));
+                map
+            })
+        ).await?;
+
+        if let Some(ValueType::Map(map)) = &status_result.data {
+            if let Some(ValueType::String(status)) = map.get("status") {
+                println!("Job status: {}", status);
+                if status == "done" || status == "failed" {
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    }
+
     // Shutdown node
     println!("\nShutting down node...");
     node.stop().await?;
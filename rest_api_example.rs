@@ -1,11 +1,14 @@
-use runar_macros::{action, gateway, init, main, rest_api, service};
+use runar_macros::{action, gateway, init, main, rest_api, service, subscribe};
 use runar_node::{
     anyhow::{self, Result},
     async_trait::async_trait,
     node::NodeConfig,
+    services::ValueType,
     Node,
 };
 use runar_gateway::GatewayConfig;
+use runar_gateway::auth::{JwtAlgorithm, JwtConfig, JwtMiddleware};
+use runar_gateway::metering::{CostModel, MeteringConfig, MeteringMiddleware};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -76,48 +79,90 @@ impl InvoiceService {
             paid: false,
             due_date: req.due_date,
         };
-        
+
         let mut invoices = self.invoices.write().await;
         invoices.insert(invoice.id, invoice.clone());
-        
+        drop(invoices);
+
+        self.publish_change("created", &invoice).await?;
+
         Ok(invoice)
     }
-    
+
     #[action]
     pub async fn update_invoice(&self, id: Uuid, req: UpdateInvoiceRequest) -> Result<Invoice> {
         let mut invoices = self.invoices.write().await;
-        
+
         let invoice = invoices
             .get_mut(&id)
             .ok_or_else(|| anyhow::anyhow!("Invoice not found"))?;
-        
+
         if let Some(amount) = req.amount {
             invoice.amount = amount;
         }
-        
+
         if let Some(paid) = req.paid {
             invoice.paid = paid;
         }
-        
+
         if let Some(due_date) = req.due_date {
             invoice.due_date = due_date;
         }
-        
-        Ok(invoice.clone())
+
+        let updated = invoice.clone();
+        drop(invoices);
+
+        self.publish_change("updated", &updated).await?;
+
+        Ok(updated)
     }
-    
+
     #[action]
     pub async fn delete_invoice(&self, id: Uuid) -> Result<()> {
         let mut invoices = self.invoices.write().await;
-        
-        if invoices.remove(&id).is_none() {
+
+        let Some(invoice) = invoices.remove(&id) else {
             return Err(anyhow::anyhow!("Invoice not found"));
-        }
-        
+        };
+        drop(invoices);
+
+        self.publish_change("deleted", &invoice).await?;
+
+        Ok(())
+    }
+
+    // Notify the gateway's watch endpoint (and anything else subscribed to
+    // "invoice_service/events/*") of a create/update/delete so it can fan
+    // the change out to connected WebSocket/SSE clients.
+    async fn publish_change(&self, kind: &str, invoice: &Invoice) -> Result<()> {
+        self.context.publish(
+            &format!("invoice_service/events/{}", kind),
+            ValueType::Json(serde_json::to_value(invoice)?),
+        ).await
+    }
+
+    // Billing sink for the gateway's metering middleware: called on a flush
+    // interval with the accumulated usage for one (subject, route) pair
+    // rather than per-request, so this stays cheap even under load.
+    #[action]
+    pub async fn record_usage(&self, req: RecordUsageRequest) -> Result<()> {
+        println!(
+            "Billing: subject={} route={} requests={} cpu_seconds={:.3} cost={:.4}",
+            req.subject, req.route, req.request_count, req.cpu_seconds, req.cost
+        );
         Ok(())
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordUsageRequest {
+    pub subject: String,
+    pub route: String,
+    pub request_count: u64,
+    pub cpu_seconds: f64,
+    pub cost: f64,
+}
+
 // Define a simple customer service
 #[service(name = "customer_service")]
 pub struct CustomerService {
@@ -138,6 +183,7 @@ pub struct Customer {
     pub id: String,
     pub name: String,
     pub email: String,
+    pub avatar_url: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -164,31 +210,83 @@ impl CustomerService {
     }
     
     #[action]
-    pub async fn create_customer(&self, req: CreateCustomerRequest) -> Result<Customer> {
+    pub async fn create_customer(&self, req: CreateCustomerRequest, avatar: Option<Vec<u8>>) -> Result<Customer> {
         let id = format!("cust_{}", Uuid::new_v4().to_string().split('-').next().unwrap());
-        
+
+        // Actual storage (e.g. to blob storage) is out of scope for this
+        // example - just record that an avatar was uploaded.
+        let avatar_url = avatar.map(|bytes| format!("/avatars/{}?bytes={}", id, bytes.len()));
+
         let customer = Customer {
             id: id.clone(),
             name: req.name,
             email: req.email,
+            avatar_url,
         };
-        
+
         let mut customers = self.customers.write().await;
         customers.insert(id, customer.clone());
-        
+
         Ok(customer)
     }
 }
 
 // Define the API gateway with REST API mappings
+//
+// `openapi_route` serves a generated OpenAPI 3.0 document built from the
+// `#[rest_api]`/`#[action(METHOD, path)]` metadata below: each `:id`-style
+// path segment becomes a typed path parameter, request/response structs
+// become component schemas via their Serialize/Deserialize impls, and error
+// returns map to the documented status codes. Point Swagger UI or an
+// OpenAPI codegen tool at this route once the gateway is running.
+//
+// `metering` wraps every routed request, timing the downstream call and
+// attributing wall/CPU time plus a computed cost to the authenticated
+// subject. Usage accumulates in memory and is drained periodically by the
+// flush task spawned in `main` below rather than billed per request.
+//
+// `compression` transparently gzip-decodes request bodies sent with
+// `Content-Encoding: gzip` and gzip-encodes responses when the client sends
+// a matching `Accept-Encoding`, skipping bodies under `min_compressible_size`
+// where compression overhead isn't worth it.
 #[service]
 #[gateway(
     host = "0.0.0.0",
     port = 8080,
-    services = [InvoiceService, CustomerService]
+    services = [InvoiceService, CustomerService],
+    openapi_route = "/openapi.json",
+    middleware = [auth_middleware(), metering_middleware()],
+    compression = true,
+    min_compressible_size = 1024
 )]
 pub struct ApiGateway;
 
+// Runs ahead of metering so every routed request carries a decoded `sub`
+// claim in its context before usage is attributed to it.
+fn auth_middleware() -> JwtMiddleware {
+    JwtMiddleware::new(JwtConfig {
+        algorithm: JwtAlgorithm::HS256,
+        // In a real deployment this comes from the environment, not a
+        // literal in source.
+        verification_key: std::env::var("JWT_SIGNING_KEY")
+            .unwrap_or_else(|_| "dev-only-secret".to_string()),
+        required_claims: vec!["sub".to_string()],
+    })
+}
+
+fn metering_middleware() -> MeteringMiddleware {
+    MeteringMiddleware::new(MeteringConfig {
+        cost_model: CostModel {
+            cost_per_request: 0.0001,
+            cost_per_cpu_second: 0.002,
+        },
+        // Every route here requires a verified JWT (see auth_middleware),
+        // so there are no unauthenticated routes to exclude - keep metering
+        // all of them so the billing demo below actually has usage to flush.
+        exclude_public_routes: false,
+    })
+}
+
 #[init]
 impl ApiGateway {
     pub async fn new() -> Result<Self> {
@@ -231,6 +329,17 @@ impl ApiGateway {
         // This maps to invoice_service.delete_invoice(id)
         self.context.request("invoice_service", "delete_invoice", { id }).await
     }
+
+    // Upgrades to a WebSocket (falling back to SSE for clients that ask for
+    // `Accept: text/event-stream`) and forwards every
+    // "invoice_service/events/*" change notification as a frame until the
+    // client disconnects. The subscription is dropped - and the node-side
+    // event channel torn down with it - as soon as the socket closes.
+    // `backpressure` bounds how many unsent frames can queue for a slow
+    // client before the oldest is dropped, so one stalled watcher can't grow
+    // memory unbounded.
+    #[subscribe(path = "/invoices/watch", topic = "invoice_service/events/*", backpressure = 256)]
+    async fn watch_invoices(&self) {}
 }
 
 // Map customer service operations to REST endpoints
@@ -251,10 +360,14 @@ impl ApiGateway {
         self.context.request("customer_service", "get_customer", { id }).await
     }
     
+    // `multipart/form-data` requests have their non-file fields bound to
+    // `req` as usual and their file parts surfaced as typed parameters -
+    // here `avatar` comes from the "avatar" form field without any
+    // hand-rolled multipart parsing in the handler.
     #[action(POST, "/customers")]
-    async fn create_customer(&self, req: CreateCustomerRequest) -> Result<Customer> {
-        // This maps to customer_service.create_customer(req)
-        self.context.request("customer_service", "create_customer", { req }).await
+    async fn create_customer(&self, req: CreateCustomerRequest, avatar: Option<Vec<u8>>) -> Result<Customer> {
+        // This maps to customer_service.create_customer(req, avatar)
+        self.context.request("customer_service", "create_customer", { req, avatar }).await
     }
 }
 
@@ -281,7 +394,22 @@ async fn main() -> Result<()> {
     
     // Start the node which will manage all services
     node.start().await?;
-    
+
+    println!("OpenAPI spec available at http://0.0.0.0:8080/openapi.json");
+
+    // Periodically drain the metering ledger and bill it to
+    // invoice_service.record_usage instead of dispatching a request per call
+    let billing_node = node.handle();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = billing_node.flush_metering_ledger("invoice_service", "record_usage").await {
+                eprintln!("Metering flush failed: {}", e);
+            }
+        }
+    });
+
     // Wait for the node to complete (typically runs until interrupted)
     node.wait_for_shutdown().await?;
     